@@ -17,6 +17,12 @@
 //! Using two `HashMap`s instead of one naturally brings a slight performance
 //! and memory penalty. Notably, indexing by `K2` requires two `HashMap` lookups.
 //!
+//! Like `std::collections::HashMap`, the hasher used by the two internal
+//! `HashMap`s is configurable via a third type parameter `S`, which defaults
+//! to `RandomState`. Use [`MultiMap::with_hasher`] or
+//! [`MultiMap::with_capacity_and_hasher`] to plug in a faster or
+//! deterministic hasher.
+//!
 //! ```
 //! extern crate multi_map;
 //! use multi_map::MultiMap;
@@ -40,37 +46,60 @@
 //! ```
 
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+#[cfg(feature = "serde")]
+use serde::Serialize;
 use std::borrow::Borrow;
 use std::collections::hash_map;
+use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
 use std::fmt::{self, Debug};
-use std::hash::Hash;
+use std::hash::{BuildHasher, Hash};
+
+mod ordered;
+pub use ordered::OrderedMultiMap;
+
+#[cfg(feature = "rayon")]
+mod par;
 
 #[cfg_attr(
     feature = "serde",
-    derive(Deserialize, Serialize),
-    serde(from = "HashMap<K1, (K2, V)>")
+    derive(Serialize),
+    serde(bound(
+        serialize = "K1: Serialize + Eq + Hash + Clone, \
+                      K2: Serialize + Eq + Hash + Clone, \
+                      V: Serialize, \
+                      S: BuildHasher"
+    ))
 )]
-#[derive(Eq)]
-pub struct MultiMap<K1, K2, V>
+pub struct MultiMap<K1, K2, V, S = RandomState>
 where
     K1: Eq + Hash + Clone,
     K2: Eq + Hash + Clone,
 {
     #[cfg_attr(feature = "serde", serde(flatten))]
-    value_map: HashMap<K1, (K2, V)>,
+    value_map: HashMap<K1, (K2, V), S>,
     #[cfg_attr(feature = "serde", serde(skip))]
-    key_map: HashMap<K2, K1>,
+    key_map: HashMap<K2, K1, S>,
+}
+
+impl<K1, K2, V, S> Eq for MultiMap<K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+    V: Eq,
+    S: BuildHasher,
+{
 }
 
-impl<K1, K2, V> From<HashMap<K1, (K2, V)>> for MultiMap<K1, K2, V>
+impl<K1, K2, V, S> From<HashMap<K1, (K2, V), S>> for MultiMap<K1, K2, V, S>
 where
     K1: Eq + Hash + Clone,
     K2: Eq + Hash + Clone,
+    S: BuildHasher + Clone + Default,
 {
-    fn from(tuple_map: HashMap<K1, (K2, V)>) -> Self {
-        let mut m = MultiMap::with_capacity(tuple_map.len());
+    fn from(tuple_map: HashMap<K1, (K2, V), S>) -> Self {
+        let mut m = MultiMap::with_capacity_and_hasher(tuple_map.len(), S::default());
         for (k1, (k2, v)) in tuple_map {
             m.insert(k1, k2, v);
         }
@@ -78,7 +107,79 @@ where
     }
 }
 
-impl<K1, K2, V> MultiMap<K1, K2, V>
+// A hand-written `Deserialize` rather than `#[derive]` + `#[serde(from =
+// ...)]`: deserializing into a plain `HashMap<K1, (K2, V)>` and then
+// `From`-converting it (as this crate used to) builds a whole intermediate
+// map only to re-insert every element from it into a fresh one. Visiting the `(K1, (K2, V))` stream
+// directly lets us populate `value_map` and `key_map` in the same pass,
+// cloning each `K2` exactly once, and lets us reject input where two
+// entries share a `K2` instead of silently letting the later one win.
+#[cfg(feature = "serde")]
+impl<'de, K1, K2, V, S> Deserialize<'de> for MultiMap<K1, K2, V, S>
+where
+    K1: Deserialize<'de> + Eq + Hash + Clone,
+    K2: Deserialize<'de> + Eq + Hash + Clone,
+    V: Deserialize<'de>,
+    S: BuildHasher + Clone + Default,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct MultiMapVisitor<K1, K2, V, S> {
+            marker: std::marker::PhantomData<(K1, K2, V, S)>,
+        }
+
+        impl<'de, K1, K2, V, S> Visitor<'de> for MultiMapVisitor<K1, K2, V, S>
+        where
+            K1: Deserialize<'de> + Eq + Hash + Clone,
+            K2: Deserialize<'de> + Eq + Hash + Clone,
+            V: Deserialize<'de>,
+            S: BuildHasher + Clone + Default,
+        {
+            type Value = MultiMap<K1, K2, V, S>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str(
+                    "a map of K1 to (K2, V), with no two entries sharing a K1 or a K2",
+                )
+            }
+
+            fn visit_map<A>(self, mut access: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut map = MultiMap::with_capacity_and_hasher(
+                    access.size_hint().unwrap_or(0),
+                    S::default(),
+                );
+                while let Some((key_one, (key_two, value))) =
+                    access.next_entry::<K1, (K2, V)>()?
+                {
+                    if map.value_map.contains_key(&key_one) {
+                        return Err(de::Error::custom(
+                            "duplicate primary (K1) key in MultiMap",
+                        ));
+                    }
+                    if map.key_map.contains_key(&key_two) {
+                        return Err(de::Error::custom(
+                            "duplicate secondary (K2) key in MultiMap",
+                        ));
+                    }
+                    map.key_map.insert(key_two.clone(), key_one.clone());
+                    map.value_map.insert(key_one, (key_two, value));
+                }
+                Ok(map)
+            }
+        }
+
+        deserializer.deserialize_map(MultiMapVisitor {
+            marker: std::marker::PhantomData,
+        })
+    }
+}
+
+impl<K1, K2, V> MultiMap<K1, K2, V, RandomState>
 where
     K1: Eq + Hash + Clone,
     K2: Eq + Hash + Clone,
@@ -93,7 +194,7 @@ where
     /// that when an item is removed using the `K1` key, the appropriate `K2`
     /// value is available so the `K2->K1` map can be removed from the second
     /// HashMap, to keep them in sync.
-    pub fn new() -> MultiMap<K1, K2, V> {
+    pub fn new() -> MultiMap<K1, K2, V, RandomState> {
         MultiMap {
             value_map: HashMap::new(),
             key_map: HashMap::new(),
@@ -103,13 +204,51 @@ where
     /// Creates an empty MultiMap with the specified capacity.
     ///
     /// The multi map will be able to hold at least `capacity` elements without reallocating. If `capacity` is 0, the multi map will not allocate.
-    pub fn with_capacity(capacity: usize) -> MultiMap<K1, K2, V> {
+    pub fn with_capacity(capacity: usize) -> MultiMap<K1, K2, V, RandomState> {
         MultiMap {
             value_map: HashMap::with_capacity(capacity),
             key_map: HashMap::with_capacity(capacity),
         }
     }
+}
+
+impl<K1, K2, V, S> MultiMap<K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+    S: BuildHasher + Clone,
+{
+    /// Creates an empty MultiMap which will use the given hash builder for
+    /// both of its internal `HashMap`s, so that hashing of `K1` and `K2`
+    /// stays consistent between them.
+    pub fn with_hasher(hash_builder: S) -> MultiMap<K1, K2, V, S> {
+        MultiMap {
+            value_map: HashMap::with_hasher(hash_builder.clone()),
+            key_map: HashMap::with_hasher(hash_builder),
+        }
+    }
+
+    /// Creates an empty MultiMap with the specified capacity, using `hash_builder`
+    /// for both of its internal `HashMap`s.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> MultiMap<K1, K2, V, S> {
+        MultiMap {
+            value_map: HashMap::with_capacity_and_hasher(capacity, hash_builder.clone()),
+            key_map: HashMap::with_capacity_and_hasher(capacity, hash_builder),
+        }
+    }
+
+    /// Returns a reference to the MultiMap's `BuildHasher`.
+    pub fn hasher(&self) -> &S {
+        self.value_map.hasher()
+    }
+}
 
+impl<K1, K2, V, S> MultiMap<K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+    S: BuildHasher,
+{
     /// Insert an item into the MultiMap. You must supply both keys to insert
     /// an item. The keys cannot be modified at a later date, so if you only
     /// have one key at this time, use a placeholder value for the second key
@@ -120,6 +259,42 @@ where
         self.value_map.insert(key_one, (key_two, value));
     }
 
+    /// Gets the given pair of keys' corresponding entry in the MultiMap for
+    /// in-place manipulation, like `std::collections::HashMap::entry`.
+    ///
+    /// Because an entry is defined by *both* keys together, the entry is only
+    /// `Occupied` if `key_one` is already present *and* stored alongside
+    /// `key_two`. Otherwise it is `Vacant`, and inserting into it drops
+    /// whichever stale entry would otherwise desync the two internal maps:
+    /// if `key_two` already belongs to a *different* primary key, that
+    /// other entry can't keep it (a `(K2, V)` pair can't exist without a
+    /// valid `K2`), so it is evicted outright; if `key_one` is already present under a different
+    /// secondary key, that old `key_two` stops resolving via
+    /// [`get_alt`](MultiMap::get_alt). Either way, the two internal maps
+    /// never end up disagreeing about which keys belong together.
+    pub fn entry(&mut self, key_one: K1, key_two: K2) -> Entry<'_, K1, K2, V, S> {
+        match self.key_map.get(&key_two).cloned() {
+            Some(ref existing) if *existing == key_one && self.value_map.contains_key(&key_one) => {
+                let value = &mut self.value_map.get_mut(&key_one).unwrap().1;
+                Entry::Occupied(OccupiedEntry { value })
+            }
+            Some(old_owner) => {
+                self.value_map.remove(&old_owner);
+                self.key_map.remove(&key_two);
+                Entry::Vacant(VacantEntry {
+                    map: self,
+                    key_one,
+                    key_two,
+                })
+            }
+            None => Entry::Vacant(VacantEntry {
+                map: self,
+                key_one,
+                key_two,
+            }),
+        }
+    }
+
     /// Obtain a reference to an item in the MultiMap using the primary key,
     /// just like a HashMap.
     pub fn get(&self, key: &K1) -> Option<&V> {
@@ -252,6 +427,50 @@ where
         result
     }
 
+    /// Changes the secondary key of an entry from `old_key_two` to
+    /// `new_key_two`, in place. Unlike a remove-then-reinsert, this never
+    /// clones or moves the value `V`. Returns `false` (and leaves the
+    /// MultiMap unchanged) if `old_key_two` isn't present, or if
+    /// `new_key_two` already belongs to a *different* entry.
+    pub fn rekey_alt(&mut self, old_key_two: &K2, new_key_two: K2) -> bool {
+        let mut success = false;
+        if let Some(key_one) = self.key_map.get(old_key_two).cloned() {
+            let clobbers_other_entry = self
+                .key_map
+                .get(&new_key_two)
+                .is_some_and(|owner| *owner != key_one);
+            if !clobbers_other_entry {
+                self.key_map.remove(old_key_two);
+                self.key_map.insert(new_key_two.clone(), key_one.clone());
+                if let Some(pair) = self.value_map.get_mut(&key_one) {
+                    pair.0 = new_key_two;
+                }
+                success = true;
+            }
+        }
+        success
+    }
+
+    /// Changes the primary key of an entry from `old_key_one` to
+    /// `new_key_one`, without cloning the value `V`. The entry's `(K2, V)`
+    /// pair is moved to the slot for `new_key_one`, and its secondary key is
+    /// re-pointed at `new_key_one`. Returns `false` (and leaves the MultiMap
+    /// unchanged) if `old_key_one` isn't present, or if `new_key_one`
+    /// already belongs to a *different* entry.
+    pub fn rekey(&mut self, old_key_one: &K1, new_key_one: K1) -> bool {
+        let mut success = false;
+        let clobbers_other_entry =
+            *old_key_one != new_key_one && self.value_map.contains_key(&new_key_one);
+        if !clobbers_other_entry {
+            if let Some(pair) = self.value_map.remove(old_key_one) {
+                self.key_map.insert(pair.0.clone(), new_key_one.clone());
+                self.value_map.insert(new_key_one, pair);
+                success = true;
+            }
+        }
+        success
+    }
+
     /// Iterate through all the values in the MultiMap in random order.
     /// Note that the values
     /// are `(K2, V)` tuples, not `V`, as you would get with a HashMap.
@@ -260,23 +479,91 @@ where
             base: self.value_map.iter(),
         }
     }
+
+    /// Retains only the entries for which `f` returns `true`, removing the
+    /// rest from both internal HashMaps so they stay in sync.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K1, &K2, &mut V) -> bool,
+    {
+        let key_map = &mut self.key_map;
+        self.value_map.retain(|key_one, (key_two, value)| {
+            let keep = f(key_one, key_two, value);
+            if !keep {
+                key_map.remove(key_two);
+            }
+            keep
+        });
+    }
+
+    /// Clears the MultiMap, returning all `(K1, K2, V)` triples as an
+    /// iterator. Both internal HashMaps are emptied immediately, so dropping
+    /// the iterator before it is fully consumed still leaves the MultiMap
+    /// empty rather than partially drained.
+    pub fn drain(&mut self) -> Drain<'_, K1, K2, V> {
+        self.key_map.clear();
+        Drain {
+            base: self.value_map.drain(),
+        }
+    }
+
+    /// Removes and returns every entry for which `f` returns `true`, as an
+    /// iterator of `(K1, K2, V)` triples.
+    ///
+    /// This deliberately deviates from `std`/`hashbrown`'s `extract_if`,
+    /// which drives `f` lazily as the returned iterator is consumed: here
+    /// `f` is run against every entry up front (so it may freely mutate
+    /// values it chooses to keep), and the matching entries are removed
+    /// from both internal HashMaps immediately; the returned iterator just
+    /// yields the ones already extracted. That still satisfies the actual
+    /// requirement — the two maps never disagree, no matter how much of
+    /// the iterator gets consumed — it just gets there by finishing the
+    /// removal before the iterator is even returned, rather than lazily as
+    /// it's driven. Dropping the iterator part-way through therefore still
+    /// leaves the MultiMap's two maps in sync, simply discarding whichever
+    /// extracted values hadn't been yielded yet.
+    pub fn extract_if<F>(&mut self, mut f: F) -> ExtractIf<K1, K2, V>
+    where
+        F: FnMut(&K1, &K2, &mut V) -> bool,
+    {
+        let mut matched_keys = Vec::new();
+        for (key_one, (key_two, value)) in self.value_map.iter_mut() {
+            if f(key_one, key_two, value) {
+                matched_keys.push(key_one.clone());
+            }
+        }
+
+        let mut extracted = Vec::with_capacity(matched_keys.len());
+        for key_one in matched_keys {
+            if let Some((key_two, value)) = self.value_map.remove(&key_one) {
+                self.key_map.remove(&key_two);
+                extracted.push((key_one, key_two, value));
+            }
+        }
+
+        ExtractIf {
+            base: extracted.into_iter(),
+        }
+    }
 }
 
-impl<K1, K2, V: Eq> PartialEq for MultiMap<K1, K2, V>
+impl<K1, K2, V: Eq, S> PartialEq for MultiMap<K1, K2, V, S>
 where
     K1: Eq + Hash + Clone,
     K2: Eq + Hash + Clone,
+    S: BuildHasher,
 {
-    fn eq(&self, other: &MultiMap<K1, K2, V>) -> bool {
+    fn eq(&self, other: &MultiMap<K1, K2, V, S>) -> bool {
         self.value_map.eq(&other.value_map)
     }
 }
 
-impl<K1, K2, V> fmt::Debug for MultiMap<K1, K2, V>
+impl<K1, K2, V, S> fmt::Debug for MultiMap<K1, K2, V, S>
 where
     K1: Eq + Hash + Clone + Debug,
     K2: Eq + Hash + Clone + Debug,
     V: Debug,
+    S: BuildHasher,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_map()
@@ -289,15 +576,17 @@ where
     }
 }
 
-impl<K1, K2, V> Default for MultiMap<K1, K2, V>
+impl<K1, K2, V, S> Default for MultiMap<K1, K2, V, S>
 where
     K1: Eq + Hash + Clone,
     K2: Eq + Hash + Clone,
+    S: BuildHasher + Clone + Default,
 {
-    /// Creates an empty `MultiMap<K1, K2, V>`
+    /// Creates an empty `MultiMap<K1, K2, V, S>`, using `S::default()` to
+    /// build the hasher shared by both internal `HashMap`s.
     #[inline]
-    fn default() -> MultiMap<K1, K2, V> {
-        MultiMap::new()
+    fn default() -> MultiMap<K1, K2, V, S> {
+        MultiMap::with_hasher(S::default())
     }
 }
 
@@ -330,10 +619,11 @@ pub struct IntoIter<K1, K2, V> {
 //     }
 // }
 
-impl<K1, K2, V> IntoIterator for MultiMap<K1, K2, V>
+impl<K1, K2, V, S> IntoIterator for MultiMap<K1, K2, V, S>
 where
     K1: Eq + Hash + Clone,
     K2: Eq + Hash + Clone,
+    S: BuildHasher,
 {
     type Item = (K1, (K2, V));
     type IntoIter = IntoIter<K1, K2, V>;
@@ -349,10 +639,11 @@ where
     }
 }
 
-impl<'a, K1, K2, V> IntoIterator for &'a MultiMap<K1, K2, V>
+impl<'a, K1, K2, V, S> IntoIterator for &'a MultiMap<K1, K2, V, S>
 where
     K1: Eq + Hash + Clone,
     K2: Eq + Hash + Clone,
+    S: BuildHasher,
 {
     type Item = (&'a K1, &'a (K2, V));
     type IntoIter = Iter<'a, K1, K2, V>;
@@ -387,6 +678,145 @@ impl<K1, K2, V> Iterator for IntoIter<K1, K2, V> {
     }
 }
 
+/// A draining iterator over the entries of a `MultiMap`, yielding `(K1, K2,
+/// V)` triples.
+///
+/// This `struct` is created by the [`drain`](MultiMap::drain) method on
+/// [`MultiMap`]. See its documentation for more.
+pub struct Drain<'a, K1: 'a, K2: 'a, V: 'a> {
+    base: hash_map::Drain<'a, K1, (K2, V)>,
+}
+
+impl<'a, K1, K2, V> Iterator for Drain<'a, K1, K2, V> {
+    type Item = (K1, K2, V);
+
+    fn next(&mut self) -> Option<(K1, K2, V)> {
+        self.base.next().map(|(key_one, (key_two, value))| (key_one, key_two, value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.base.size_hint()
+    }
+}
+
+/// An iterator over the entries extracted from a `MultiMap` by
+/// [`extract_if`](MultiMap::extract_if), yielding `(K1, K2, V)` triples.
+pub struct ExtractIf<K1, K2, V> {
+    base: std::vec::IntoIter<(K1, K2, V)>,
+}
+
+impl<K1, K2, V> Iterator for ExtractIf<K1, K2, V> {
+    type Item = (K1, K2, V);
+
+    fn next(&mut self) -> Option<(K1, K2, V)> {
+        self.base.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.base.size_hint()
+    }
+}
+
+/// A view into a single entry in a `MultiMap`, identified by both its
+/// primary and secondary key, which may be either vacant or occupied.
+///
+/// This `enum` is constructed from the [`entry`](MultiMap::entry) method on
+/// [`MultiMap`].
+pub enum Entry<'a, K1, K2, V, S = RandomState>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// An occupied entry, where `key_one` is already present alongside
+    /// `key_two`.
+    Occupied(OccupiedEntry<'a, V>),
+    /// A vacant entry, ready to be filled via [`or_insert`](Entry::or_insert)
+    /// or [`or_insert_with`](Entry::or_insert_with).
+    Vacant(VacantEntry<'a, K1, K2, V, S>),
+}
+
+impl<'a, K1, K2, V, S> Entry<'a, K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Ensures a value is in the entry by inserting `default` if empty, and
+    /// returns a mutable reference to the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.value,
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of `default`
+    /// if empty, and returns a mutable reference to the value.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.value,
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts into the map.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(entry) => {
+                f(entry.value);
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+/// A view into an occupied entry in a `MultiMap`. See [`Entry`].
+pub struct OccupiedEntry<'a, V> {
+    value: &'a mut V,
+}
+
+/// A view into a vacant entry in a `MultiMap`. See [`Entry`].
+pub struct VacantEntry<'a, K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    map: &'a mut MultiMap<K1, K2, V, S>,
+    key_one: K1,
+    key_two: K2,
+}
+
+impl<'a, K1, K2, V, S> VacantEntry<'a, K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Sets the value of the entry, inserting under both keys, and returns a
+    /// mutable reference to it.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let key_one = self.key_one.clone();
+        // `key_one` may already be present under a different, stale
+        // `key_two` (e.g. `map.insert(1, "Old", ..); map.entry(1,
+        // "New")...`); drop that old `key_two -> key_one` mapping first so
+        // it doesn't keep resolving via `get_alt` after this insert.
+        if let Some((old_key_two, _)) = self.map.value_map.get(&key_one) {
+            if *old_key_two != self.key_two {
+                let old_key_two = old_key_two.clone();
+                self.map.key_map.remove(&old_key_two);
+            }
+        }
+        self.map.insert(self.key_one, self.key_two, value);
+        &mut self.map.value_map.get_mut(&key_one).unwrap().1
+    }
+}
+
 #[macro_export]
 /// Create a `MultiMap` from a list of key-value tuples
 ///
@@ -546,6 +976,32 @@ mod test {
         assert_eq!(deserialized.get_alt(&"Four"), None);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_duplicate_alt_key() {
+        use super::MultiMap;
+
+        // Two different primary keys (1 and 2) both claim the secondary
+        // key "One" - this must be a deserialize error, not a silently
+        // desynced MultiMap.
+        let json = r#"{"1":["One","Eins"],"2":["One","Zwei"]}"#;
+        let result: Result<MultiMap<i32, &str, String>, _> = serde_json::from_str(json);
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_duplicate_primary_key() {
+        use super::MultiMap;
+
+        // The same primary key (1) appears twice with different secondary
+        // keys - this must be a deserialize error, not a silent overwrite
+        // that leaves the first entry's secondary key stranded in `key_map`.
+        let json = r#"{"1":["A","first"],"1":["B","second"]}"#;
+        let result: Result<MultiMap<i32, &str, String>, _> = serde_json::from_str(json);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn macro_test() {
         use super::MultiMap;
@@ -594,4 +1050,179 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn custom_hasher_test() {
+        use super::MultiMap;
+        use std::collections::hash_map::RandomState;
+
+        let mut map: MultiMap<i32, &str, String, RandomState> =
+            MultiMap::with_hasher(RandomState::new());
+        map.insert(1, "One", String::from("Eins"));
+        map.insert(2, "Two", String::from("Zwei"));
+
+        assert_eq!(*map.get(&1).unwrap(), String::from("Eins"));
+        assert_eq!(*map.get_alt(&"Two").unwrap(), String::from("Zwei"));
+
+        let map: MultiMap<i32, &str, String, RandomState> =
+            MultiMap::with_capacity_and_hasher(16, RandomState::new());
+        assert_eq!(map.get(&1), None);
+    }
+
+    #[test]
+    fn entry_test() {
+        use super::MultiMap;
+
+        let mut map = MultiMap::new();
+        map.insert(1, "One", String::from("Eins"));
+
+        // Vacant: new pair of keys, inserted.
+        map.entry(2, "Two").or_insert_with(|| String::from("Zwei"));
+        assert_eq!(*map.get(&2).unwrap(), String::from("Zwei"));
+        assert_eq!(*map.get_alt(&"Two").unwrap(), String::from("Zwei"));
+
+        // Occupied: existing pair of keys, `or_insert` is a no-op and
+        // `and_modify` runs.
+        map.entry(1, "One")
+            .and_modify(|v| v.push('!'))
+            .or_insert_with(|| String::from("unused"));
+        assert_eq!(*map.get(&1).unwrap(), String::from("Eins!"));
+
+        // `key_two` already belongs to a different `key_one`: that old
+        // entry (1) can't keep claiming "One" once 3 does, so it is
+        // evicted outright rather than left behind with a stale `key_two`
+        // embedded in its `value_map` slot.
+        map.entry(3, "One").or_insert(String::from("Drei"));
+        assert_eq!(*map.get(&3).unwrap(), String::from("Drei"));
+        assert_eq!(*map.get_alt(&"One").unwrap(), String::from("Drei"));
+        assert_eq!(map.get(&1), None);
+
+        // Removing the old owner (6) afterwards is a no-op, since it no
+        // longer exists at all: it must not drop the new owner's "Six"
+        // from `key_map` or otherwise corrupt it.
+        map.insert(6, "Six", String::from("Sechs"));
+        map.entry(7, "Six").or_insert(String::from("Sieben"));
+        assert_eq!(map.get(&6), None);
+        assert_eq!(*map.get(&7).unwrap(), String::from("Sieben"));
+        assert_eq!(*map.get_alt(&"Six").unwrap(), String::from("Sieben"));
+        map.remove(&6);
+        assert_eq!(*map.get_alt(&"Six").unwrap(), String::from("Sieben"));
+
+        // `key_one` already exists under a different `key_two`: this entry
+        // is `Vacant` (since `key_two` doesn't match), so `or_insert`
+        // overwrites the value as usual, but the stale `key_map` entry for
+        // the old secondary key is also dropped so it doesn't keep
+        // resolving to this entry's new value.
+        map.insert(4, "Old", String::from("Vier"));
+        map.entry(4, "New").or_insert(String::from("Vier!"));
+        assert_eq!(map.get_alt(&"Old"), None);
+        assert_eq!(*map.get_alt(&"New").unwrap(), String::from("Vier!"));
+        assert_eq!(*map.get(&4).unwrap(), String::from("Vier!"));
+    }
+
+    #[test]
+    fn rekey_test() {
+        use super::MultiMap;
+
+        let mut map = MultiMap::new();
+        map.insert(1, "One", String::from("Eins"));
+        map.insert(2, "Two", String::from("Zwei"));
+
+        assert!(map.rekey_alt(&"One", "Uno"));
+        assert_eq!(map.get_alt(&"One"), None);
+        assert_eq!(*map.get_alt(&"Uno").unwrap(), String::from("Eins"));
+        assert_eq!(*map.get(&1).unwrap(), String::from("Eins"));
+
+        // Refusing to clobber another entry's secondary key.
+        assert!(!map.rekey_alt(&"Uno", "Two"));
+        assert_eq!(*map.get_alt(&"Uno").unwrap(), String::from("Eins"));
+
+        // Absent key is rejected.
+        assert!(!map.rekey_alt(&"Missing", "Whatever"));
+
+        assert!(map.rekey(&1, 10));
+        assert_eq!(map.get(&1), None);
+        assert_eq!(*map.get(&10).unwrap(), String::from("Eins"));
+        assert_eq!(*map.get_alt(&"Uno").unwrap(), String::from("Eins"));
+
+        // Refusing to clobber another entry's primary key.
+        assert!(!map.rekey(&10, 2));
+        assert_eq!(*map.get(&10).unwrap(), String::from("Eins"));
+
+        // Absent key is rejected.
+        assert!(!map.rekey(&99, 100));
+    }
+
+    #[test]
+    fn retain_test() {
+        use super::MultiMap;
+
+        let mut map = MultiMap::new();
+        map.insert(1, "One", 1);
+        map.insert(2, "Two", 2);
+        map.insert(3, "Three", 3);
+
+        map.retain(|_, _, v| *v % 2 == 1);
+
+        assert_eq!(map.get(&1), Some(&1));
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map.get(&3), Some(&3));
+        assert_eq!(map.get_alt(&"Two"), None);
+        assert_eq!(map.get_alt(&"Three"), Some(&3));
+    }
+
+    #[test]
+    fn drain_test() {
+        use super::MultiMap;
+
+        let mut map = MultiMap::new();
+        map.insert(1, "One", String::from("Eins"));
+        map.insert(2, "Two", String::from("Zwei"));
+
+        let mut drained: Vec<_> = map.drain().collect();
+        drained.sort();
+        assert_eq!(
+            drained,
+            vec![
+                (1, "One", String::from("Eins")),
+                (2, "Two", String::from("Zwei")),
+            ]
+        );
+        assert!(map.get(&1).is_none());
+        assert!(map.get_alt(&"Two").is_none());
+
+        // Partially consuming and dropping a Drain still leaves the
+        // MultiMap empty, since both maps are cleared up front.
+        map.insert(3, "Three", String::from("Drei"));
+        map.insert(4, "Four", String::from("Vier"));
+        {
+            let mut drain = map.drain();
+            drain.next();
+        }
+        assert!(map.get(&3).is_none());
+        assert!(map.get(&4).is_none());
+        assert!(map.get_alt(&"Four").is_none());
+    }
+
+    #[test]
+    fn extract_if_test() {
+        use super::MultiMap;
+
+        let mut map = MultiMap::new();
+        map.insert(1, "One", 1);
+        map.insert(2, "Two", 2);
+        map.insert(3, "Three", 3);
+        map.insert(4, "Four", 4);
+
+        let mut extracted: Vec<_> = map.extract_if(|_, _, v| *v % 2 == 0).collect();
+        extracted.sort();
+
+        assert_eq!(extracted, vec![(2, "Two", 2), (4, "Four", 4)]);
+        assert_eq!(map.get(&1), Some(&1));
+        assert_eq!(map.get(&3), Some(&3));
+        assert!(map.get(&2).is_none());
+        assert!(map.get(&4).is_none());
+        assert!(map.get_alt(&"Two").is_none());
+        assert!(map.get_alt(&"Four").is_none());
+    }
 }
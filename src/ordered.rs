@@ -0,0 +1,380 @@
+//! An insertion-order-preserving variant of [`MultiMap`](crate::MultiMap),
+//! modeled on the `indexmap` crate.
+//!
+//! [`OrderedMultiMap`] keeps every `(K1, K2, V)` triple in a single `Vec`
+//! ("slots") and has both internal `HashMap`s store the *index* of a slot
+//! rather than owning the value directly. This makes iteration order
+//! deterministic (insertion order, modulo any `swap_remove` calls) instead of
+//! depending on hashing, at the cost of an extra indirection on lookup.
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+
+/// Like [`MultiMap`](crate::MultiMap), but preserves insertion order and
+/// supports positional access via [`get_index`](OrderedMultiMap::get_index).
+pub struct OrderedMultiMap<K1, K2, V, S = RandomState>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+{
+    slots: Vec<(K1, K2, V)>,
+    value_map: HashMap<K1, usize, S>,
+    key_map: HashMap<K2, usize, S>,
+}
+
+impl<K1, K2, V> OrderedMultiMap<K1, K2, V, RandomState>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+{
+    /// Creates a new, empty `OrderedMultiMap`.
+    pub fn new() -> OrderedMultiMap<K1, K2, V, RandomState> {
+        OrderedMultiMap {
+            slots: Vec::new(),
+            value_map: HashMap::new(),
+            key_map: HashMap::new(),
+        }
+    }
+
+    /// Creates an empty `OrderedMultiMap` with the specified capacity.
+    pub fn with_capacity(capacity: usize) -> OrderedMultiMap<K1, K2, V, RandomState> {
+        OrderedMultiMap {
+            slots: Vec::with_capacity(capacity),
+            value_map: HashMap::with_capacity(capacity),
+            key_map: HashMap::with_capacity(capacity),
+        }
+    }
+}
+
+impl<K1, K2, V, S> OrderedMultiMap<K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+    S: BuildHasher + Clone,
+{
+    /// Creates an empty `OrderedMultiMap` which will use the given hash
+    /// builder for both of its internal `HashMap`s.
+    pub fn with_hasher(hash_builder: S) -> OrderedMultiMap<K1, K2, V, S> {
+        OrderedMultiMap {
+            slots: Vec::new(),
+            value_map: HashMap::with_hasher(hash_builder.clone()),
+            key_map: HashMap::with_hasher(hash_builder),
+        }
+    }
+
+    /// Creates an empty `OrderedMultiMap` with the specified capacity, using
+    /// `hash_builder` for both of its internal `HashMap`s.
+    pub fn with_capacity_and_hasher(
+        capacity: usize,
+        hash_builder: S,
+    ) -> OrderedMultiMap<K1, K2, V, S> {
+        OrderedMultiMap {
+            slots: Vec::with_capacity(capacity),
+            value_map: HashMap::with_capacity_and_hasher(capacity, hash_builder.clone()),
+            key_map: HashMap::with_capacity_and_hasher(capacity, hash_builder),
+        }
+    }
+}
+
+impl<K1, K2, V, S> OrderedMultiMap<K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Inserts an item, keyed on both `key_one` and `key_two`. If `key_one`
+    /// is already present its slot is updated in place (keeping its
+    /// position), otherwise a new slot is appended at the end. If `key_two`
+    /// already belongs to a *different* slot, that slot can't keep it (a
+    /// slot can't exist without a valid `key_two`, and keys can't be
+    /// modified in place), so it is evicted outright via
+    /// [`swap_remove`](Self::swap_remove) before this insert proceeds.
+    pub fn insert(&mut self, key_one: K1, key_two: K2, value: V) {
+        if let Some(&stale_index) = self.key_map.get(&key_two) {
+            if self.value_map.get(&key_one) != Some(&stale_index) {
+                self.swap_remove_index(stale_index);
+            }
+        }
+        if let Some(&index) = self.value_map.get(&key_one) {
+            let old_key_two = self.slots[index].1.clone();
+            if old_key_two != key_two {
+                self.key_map.remove(&old_key_two);
+                self.key_map.insert(key_two.clone(), index);
+            }
+            self.slots[index] = (key_one, key_two, value);
+        } else {
+            let index = self.slots.len();
+            self.value_map.insert(key_one.clone(), index);
+            self.key_map.insert(key_two.clone(), index);
+            self.slots.push((key_one, key_two, value));
+        }
+    }
+
+    /// Obtain a reference to an item using the primary key.
+    pub fn get(&self, key: &K1) -> Option<&V> {
+        let index = *self.value_map.get(key)?;
+        Some(&self.slots[index].2)
+    }
+
+    /// Obtain a mutable reference to an item using the primary key.
+    pub fn get_mut(&mut self, key: &K1) -> Option<&mut V> {
+        let index = *self.value_map.get(key)?;
+        Some(&mut self.slots[index].2)
+    }
+
+    /// Obtain a reference to an item using the secondary key.
+    pub fn get_alt(&self, key: &K2) -> Option<&V> {
+        let index = *self.key_map.get(key)?;
+        Some(&self.slots[index].2)
+    }
+
+    /// Obtain a mutable reference to an item using the secondary key.
+    pub fn get_mut_alt(&mut self, key: &K2) -> Option<&mut V> {
+        let index = *self.key_map.get(key)?;
+        Some(&mut self.slots[index].2)
+    }
+
+    /// Returns the `(key_one, key_two, value)` triple stored at the given
+    /// vector position, in the map's current order.
+    pub fn get_index(&self, index: usize) -> Option<(&K1, &K2, &V)> {
+        self.slots.get(index).map(|(k1, k2, v)| (k1, k2, v))
+    }
+
+    /// Returns the vector position of the entry associated with the given
+    /// secondary key, for use with [`get_index`](Self::get_index).
+    pub fn get_index_alt<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K2: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.key_map.get(key).copied()
+    }
+
+    /// Returns true if the map contains an entry for the given primary key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K1: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.value_map.contains_key(key)
+    }
+
+    /// Returns true if the map contains an entry for the given secondary key.
+    pub fn contains_key_alt<Q>(&self, key: &Q) -> bool
+    where
+        K2: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.key_map.contains_key(key)
+    }
+
+    /// Removes the slot at `index`, filling the gap with the last slot in
+    /// the vector (if it isn't already the last one) and patching that
+    /// slot's entries in both hash maps. This is O(1) but does not preserve
+    /// order.
+    fn swap_remove_index(&mut self, index: usize) -> (K1, K2, V) {
+        let removed = self.slots.swap_remove(index);
+        self.value_map.remove(&removed.0);
+        self.key_map.remove(&removed.1);
+        if let Some((k1, k2, _)) = self.slots.get(index) {
+            self.value_map.insert(k1.clone(), index);
+            self.key_map.insert(k2.clone(), index);
+        }
+        removed
+    }
+
+    /// Removes the slot at `index`, shifting every following slot down by
+    /// one and patching their entries in both hash maps. This is O(n) but
+    /// preserves the order of the remaining entries.
+    fn shift_remove_index(&mut self, index: usize) -> (K1, K2, V) {
+        let removed = self.slots.remove(index);
+        self.value_map.remove(&removed.0);
+        self.key_map.remove(&removed.1);
+        for (k1, k2, _) in &self.slots[index..] {
+            if let Some(i) = self.value_map.get_mut(k1) {
+                *i -= 1;
+            }
+            if let Some(i) = self.key_map.get_mut(k2) {
+                *i -= 1;
+            }
+        }
+        removed
+    }
+
+    /// Removes the entry for the given primary key by swapping in the last
+    /// slot. O(1), but does not preserve the order of the remaining entries.
+    pub fn swap_remove(&mut self, key: &K1) -> Option<V> {
+        let index = *self.value_map.get(key)?;
+        Some(self.swap_remove_index(index).2)
+    }
+
+    /// Removes the entry for the given secondary key by swapping in the
+    /// last slot. O(1), but does not preserve the order of the remaining
+    /// entries.
+    pub fn swap_remove_alt(&mut self, key: &K2) -> Option<V> {
+        let index = *self.key_map.get(key)?;
+        Some(self.swap_remove_index(index).2)
+    }
+
+    /// Removes the entry for the given primary key, shifting later entries
+    /// down by one. O(n), but preserves order.
+    pub fn shift_remove(&mut self, key: &K1) -> Option<V> {
+        let index = *self.value_map.get(key)?;
+        Some(self.shift_remove_index(index).2)
+    }
+
+    /// Removes the entry for the given secondary key, shifting later
+    /// entries down by one. O(n), but preserves order.
+    pub fn shift_remove_alt(&mut self, key: &K2) -> Option<V> {
+        let index = *self.key_map.get(key)?;
+        Some(self.shift_remove_index(index).2)
+    }
+
+    /// Iterate through all the entries in the map, in order.
+    pub fn iter(&self) -> Iter<'_, K1, K2, V> {
+        Iter {
+            base: self.slots.iter(),
+        }
+    }
+}
+
+impl<K1, K2, V, S> Default for OrderedMultiMap<K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+    S: BuildHasher + Clone + Default,
+{
+    fn default() -> OrderedMultiMap<K1, K2, V, S> {
+        OrderedMultiMap::with_hasher(S::default())
+    }
+}
+
+/// An iterator over the entries of an `OrderedMultiMap`, in order.
+///
+/// This `struct` is created by the [`iter`](OrderedMultiMap::iter) method on
+/// [`OrderedMultiMap`]. See its documentation for more.
+pub struct Iter<'a, K1: 'a, K2: 'a, V: 'a> {
+    base: std::slice::Iter<'a, (K1, K2, V)>,
+}
+
+impl<'a, K1, K2, V> Iterator for Iter<'a, K1, K2, V> {
+    type Item = (&'a K1, &'a K2, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K1, &'a K2, &'a V)> {
+        self.base.next().map(|(k1, k2, v)| (k1, k2, v))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.base.size_hint()
+    }
+}
+
+impl<'a, K1, K2, V, S> IntoIterator for &'a OrderedMultiMap<K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone,
+    K2: Eq + Hash + Clone,
+{
+    type Item = (&'a K1, &'a K2, &'a V);
+    type IntoIter = Iter<'a, K1, K2, V>;
+
+    fn into_iter(self) -> Iter<'a, K1, K2, V> {
+        Iter {
+            base: self.slots.iter(),
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn basic_test() {
+        use super::OrderedMultiMap;
+
+        let mut map = OrderedMultiMap::new();
+        map.insert(1, "One", String::from("Eins"));
+        map.insert(2, "Two", String::from("Zwei"));
+        map.insert(3, "Three", String::from("Drei"));
+
+        assert_eq!(*map.get(&2).unwrap(), String::from("Zwei"));
+        assert_eq!(*map.get_alt(&"Three").unwrap(), String::from("Drei"));
+        assert_eq!(map.get_index(1), Some((&2, &"Two", &String::from("Zwei"))));
+        assert_eq!(map.get_index_alt(&"One"), Some(0));
+
+        let order: Vec<i32> = map.iter().map(|(k1, _, _)| *k1).collect();
+        assert_eq!(order, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn swap_remove_test() {
+        use super::OrderedMultiMap;
+
+        let mut map = OrderedMultiMap::new();
+        map.insert(1, "One", String::from("Eins"));
+        map.insert(2, "Two", String::from("Zwei"));
+        map.insert(3, "Three", String::from("Drei"));
+
+        // Swap-removing the first slot moves the last slot ("Three") into
+        // its place, so both hash maps must have index 0 re-pointed at it.
+        assert_eq!(map.swap_remove(&1), Some(String::from("Eins")));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get_index(0), Some((&3, &"Three", &String::from("Drei"))));
+        assert_eq!(map.get_alt(&"Three"), Some(&String::from("Drei")));
+
+        let order: Vec<i32> = map.iter().map(|(k1, _, _)| *k1).collect();
+        assert_eq!(order, vec![3, 2]);
+    }
+
+    #[test]
+    fn shift_remove_test() {
+        use super::OrderedMultiMap;
+
+        let mut map = OrderedMultiMap::new();
+        map.insert(1, "One", String::from("Eins"));
+        map.insert(2, "Two", String::from("Zwei"));
+        map.insert(3, "Three", String::from("Drei"));
+
+        assert_eq!(map.shift_remove(&1), Some(String::from("Eins")));
+        assert_eq!(map.len(), 2);
+
+        let order: Vec<i32> = map.iter().map(|(k1, _, _)| *k1).collect();
+        assert_eq!(order, vec![2, 3]);
+        assert_eq!(map.get_alt(&"Three"), Some(&String::from("Drei")));
+        assert_eq!(map.get_index(1), Some((&3, &"Three", &String::from("Drei"))));
+    }
+
+    #[test]
+    fn insert_steals_key_two_test() {
+        use super::OrderedMultiMap;
+
+        let mut map = OrderedMultiMap::new();
+        map.insert(1, "a", String::from("A"));
+        map.insert(2, "b", String::from("B"));
+
+        // Re-keying slot 2 to "a" steals that secondary key away from slot
+        // 1; slot 1 can't keep a `key_two` it no longer owns, so it is
+        // evicted entirely rather than left behind with a stale pointer.
+        map.insert(2, "a", String::from("B2"));
+        assert_eq!(map.get(&1), None);
+        assert_eq!(*map.get(&2).unwrap(), String::from("B2"));
+        assert_eq!(*map.get_alt(&"a").unwrap(), String::from("B2"));
+        assert_eq!(map.len(), 1);
+
+        // Removing slot 2 afterwards takes "a" with it, leaving both the
+        // map and `key_map` empty, with no stale pointer behind.
+        assert_eq!(map.shift_remove(&2), Some(String::from("B2")));
+        assert_eq!(map.get_alt(&"a"), None);
+        assert!(map.is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+//! Parallel iteration over a `MultiMap`, enabled via the `rayon` feature.
+//!
+//! `std::collections::HashMap` already implements `rayon`'s
+//! `IntoParallelIterator`/`ParallelDrainFull` traits, so these methods just
+//! delegate straight to `value_map`'s parallel iterators and flatten the
+//! `(K2, V)` tuple they yield. The existing serial `Iter`/`IntoIter` are
+//! untouched; this module is purely additive and compiles away entirely
+//! when the `rayon` feature is disabled.
+
+use crate::MultiMap;
+use rayon::prelude::*;
+use std::hash::{BuildHasher, Hash};
+
+impl<K1, K2, V, S> MultiMap<K1, K2, V, S>
+where
+    K1: Eq + Hash + Clone + Send + Sync,
+    K2: Eq + Hash + Clone + Send + Sync,
+    V: Send + Sync,
+    S: BuildHasher + Sync,
+{
+    /// Returns a `rayon` parallel iterator over `(&K1, &K2, &V)` triples.
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (&K1, &K2, &V)> {
+        self.value_map
+            .par_iter()
+            .map(|(key_one, (key_two, value))| (key_one, key_two, value))
+    }
+
+    /// Returns a `rayon` parallel iterator over `&V` references.
+    pub fn par_values(&self) -> impl ParallelIterator<Item = &V> {
+        self.value_map.par_iter().map(|(_, (_, value))| value)
+    }
+
+    /// Returns a `rayon` parallel iterator over `&mut V` references.
+    pub fn par_values_mut(&mut self) -> impl ParallelIterator<Item = &mut V> {
+        self.value_map.par_iter_mut().map(|(_, (_, value))| value)
+    }
+
+    /// Drains the MultiMap in parallel, returning a `rayon` parallel
+    /// iterator over the `(K1, K2, V)` triples that were removed. Both
+    /// internal HashMaps are emptied up front, same as [`drain`](MultiMap::drain).
+    pub fn par_drain(&mut self) -> impl ParallelIterator<Item = (K1, K2, V)> + '_ {
+        self.key_map.clear();
+        self.value_map
+            .par_drain()
+            .map(|(key_one, (key_two, value))| (key_one, key_two, value))
+    }
+}
+
+mod test {
+    #[test]
+    fn par_iter_test() {
+        use super::super::MultiMap;
+        use rayon::prelude::*;
+
+        let mut map = MultiMap::new();
+        map.insert(1, "One", 10);
+        map.insert(2, "Two", 20);
+        map.insert(3, "Three", 30);
+
+        let sum: i32 = map.par_values().sum();
+        assert_eq!(sum, 60);
+
+        map.par_values_mut().for_each(|v| *v += 1);
+        assert_eq!(map.get(&1), Some(&11));
+
+        let mut triples: Vec<(i32, &str, i32)> = map
+            .par_iter()
+            .map(|(k1, k2, v)| (*k1, *k2, *v))
+            .collect();
+        triples.sort();
+        assert_eq!(triples, vec![(1, "One", 11), (2, "Two", 21), (3, "Three", 31)]);
+
+        let mut drained: Vec<(i32, &str, i32)> = map.par_drain().collect();
+        drained.sort();
+        assert_eq!(drained, vec![(1, "One", 11), (2, "Two", 21), (3, "Three", 31)]);
+        assert!(map.get(&1).is_none());
+        assert!(map.get_alt(&"Three").is_none());
+    }
+}